@@ -21,11 +21,84 @@ use crate::util::yield_once;
 /// or in the `KeyValueStoreView`.
 const MAX_KEY_SIZE: usize = 900;
 
+/// The maximum size of a single value written through [`AppStateStore::write_batch`].
+/// Values larger than this are rejected with `ViewError::ValueTooLong` rather than
+/// being passed down to the host, where a similarly-sized limit would otherwise be
+/// enforced deep inside the DynamoDb/`KeyValueStoreView` layers.
+const MAX_VALUE_SIZE: usize = 2_000_000;
+
+/// Checks `value` against [`AppStateStore`]'s `MAX_VALUE_SIZE`. A free function,
+/// separate from the trait impl, so it can be called without a host to call into.
+fn validate_value_size(value: &[u8]) -> Result<(), ViewError> {
+    ensure!(value.len() <= MAX_VALUE_SIZE, ViewError::ValueTooLong);
+    Ok(())
+}
+
+/// Checks a running batch size against [`AppStateStore::MAX_BATCH_SIZE`].
+fn validate_batch_size(batch_size: usize) -> Result<(), ViewError> {
+    ensure!(
+        batch_size <= AppStateStore::MAX_BATCH_SIZE,
+        ViewError::BatchTooLarge
+    );
+    Ok(())
+}
+
+/// A monotonically increasing per-key version token maintained by the host and
+/// bumped on every successful `Put`/`Delete` to that key. Returned by
+/// [`AppStateStore::read_value_versioned`] and consumed by
+/// [`AppStateStore::write_batch_with_checks`] as an optimistic-concurrency
+/// precondition.
+pub type Version = u64;
+
+/// Configurable ceilings on the storage a single application may consume, on top
+/// of the fixed per-key/per-value/per-batch limits.
+#[derive(Clone, Debug)]
+pub struct StorageQuota {
+    /// The maximum number of live keys the application may have stored at once.
+    pub max_keys: usize,
+    /// The maximum aggregate number of bytes (keys plus values) the application
+    /// may have stored at once.
+    pub max_total_bytes: usize,
+}
+
+impl Default for StorageQuota {
+    fn default() -> Self {
+        StorageQuota {
+            max_keys: usize::MAX,
+            max_total_bytes: usize::MAX,
+        }
+    }
+}
+
 /// A type to interface with the key value storage provided to applications.
 #[derive(Default, Clone)]
-pub struct AppStateStore;
+pub struct AppStateStore {
+    quota: StorageQuota,
+}
 
 impl AppStateStore {
+    /// The maximum aggregate size (sum of all keys and values) of a single call to
+    /// [`WritableKeyValueStore::write_batch`]. This is a separate, coarser ceiling
+    /// from `MAX_VALUE_SIZE` so that hosts can tune the cost of a whole batch
+    /// independently of the cost of any individual value.
+    pub const MAX_BATCH_SIZE: usize = 10_000_000;
+
+    /// Creates an `AppStateStore` that enforces the given [`StorageQuota`] in
+    /// addition to the fixed per-key/per-value/per-batch limits.
+    pub fn with_quota(quota: StorageQuota) -> Self {
+        AppStateStore { quota }
+    }
+
+    /// Returns the `(key_count, total_bytes)` the host currently has on record
+    /// for this application against its [`StorageQuota`]. The host is the
+    /// source of truth for this, since a wasm guest instance is re-created for
+    /// every execution and so cannot cache it across calls.
+    pub async fn storage_usage(&self) -> Result<(usize, usize), ViewError> {
+        let promise = wit::StorageUsage::new();
+        yield_once().await;
+        Ok(promise.wait())
+    }
+
     async fn find_keys_by_prefix_load(&self, key_prefix: &[u8]) -> Vec<Vec<u8>> {
         let promise = wit::FindKeys::new(key_prefix);
         yield_once().await;
@@ -37,16 +110,175 @@ impl AppStateStore {
         yield_once().await;
         promise.wait()
     }
+
+    /// Returns one page of at most `limit` keys matching `key_prefix`, starting
+    /// immediately after `cursor` (or from the start of the range if `cursor` is
+    /// `None`), together with the cursor to resume from on the next call.
+    ///
+    /// Unlike [`Self::find_keys_by_prefix`], this bounds the amount of guest
+    /// memory and host `wait()` work per call, so it can be used to walk prefix
+    /// ranges larger than what fits comfortably in linear memory at once.
+    pub async fn find_keys_by_prefix_paged(
+        &self,
+        key_prefix: &[u8],
+        cursor: Option<Vec<u8>>,
+        limit: u32,
+    ) -> Result<KeysPage, ViewError> {
+        ensure!(
+            key_prefix.len() <= Self::MAX_KEY_SIZE,
+            ViewError::KeyTooLong
+        );
+        let promise = wit::FindKeysPaged::new(key_prefix, cursor.as_deref(), limit);
+        yield_once().await;
+        let (keys, cursor) = promise.wait();
+        Ok(KeysPage { keys, cursor })
+    }
+
+    /// Returns one page of at most `limit` key-value pairs matching `key_prefix`,
+    /// starting immediately after `cursor` (or from the start of the range if
+    /// `cursor` is `None`), together with the cursor to resume from on the next
+    /// call. See [`Self::find_keys_by_prefix_paged`] for the rationale.
+    pub async fn find_key_values_by_prefix_paged(
+        &self,
+        key_prefix: &[u8],
+        cursor: Option<Vec<u8>>,
+        limit: u32,
+    ) -> Result<KeyValuesPage, ViewError> {
+        ensure!(
+            key_prefix.len() <= Self::MAX_KEY_SIZE,
+            ViewError::KeyTooLong
+        );
+        let promise = wit::FindKeyValuesPaged::new(key_prefix, cursor.as_deref(), limit);
+        yield_once().await;
+        let (key_values, cursor) = promise.wait();
+        Ok(KeyValuesPage { key_values, cursor })
+    }
+
+    /// Like [`WritableKeyValueStore::write_batch`], but the batch also carries a
+    /// set of per-key preconditions that must all hold for any of its writes to
+    /// be applied. Each entry in `checks` is `(key, Some(expected_version))`,
+    /// meaning `key` must currently be at that [`Version`], or
+    /// `(key, None)`, meaning `key` must not currently exist. If any
+    /// precondition fails, the whole batch is rejected with
+    /// `ViewError::CheckFailed` and no mutation is applied, giving applications
+    /// a foundation for lock-free counters and guarded state transitions.
+    pub async fn write_batch_with_checks(
+        &self,
+        batch: Batch,
+        checks: Vec<(Vec<u8>, Option<Version>)>,
+        base_key: &[u8],
+    ) -> Result<(), ViewError> {
+        let mut check_operations = Vec::with_capacity(checks.len());
+        for (key, expected_version) in &checks {
+            ensure!(key.len() <= Self::MAX_KEY_SIZE, ViewError::KeyTooLong);
+            check_operations.push(wit::WriteOperation::Check((key, *expected_version)));
+        }
+        self.write_batch_impl(batch, check_operations, base_key)
+            .await
+    }
+
+    /// Reads the value currently stored at `key`, if any, together with its
+    /// current [`Version`]. The version can be fed back into
+    /// [`Self::write_batch_with_checks`] as a `Check` precondition to implement
+    /// compare-and-set semantics.
+    pub async fn read_value_versioned(
+        &self,
+        key: &[u8],
+    ) -> Result<(Option<Vec<u8>>, Version), ViewError> {
+        ensure!(key.len() <= Self::MAX_KEY_SIZE, ViewError::KeyTooLong);
+        let promise = wit::ReadValueVersioned::new(key);
+        yield_once().await;
+        Ok(promise.wait())
+    }
+
+    async fn write_batch_impl(
+        &self,
+        batch: Batch,
+        operations: Vec<wit::WriteOperation<'_>>,
+        _base_key: &[u8],
+    ) -> Result<(), ViewError> {
+        // Re-bind with a shorter lifetime so the vector can also hold borrows
+        // of `batch`, which lives only in this function.
+        let mut operations: Vec<wit::WriteOperation<'_>> = operations;
+        let mut batch_size = 0usize;
+        for operation in &batch.operations {
+            match operation {
+                WriteOperation::Delete { key } => {
+                    ensure!(key.len() <= Self::MAX_KEY_SIZE, ViewError::KeyTooLong);
+                    batch_size += key.len();
+                    operations.push(wit::WriteOperation::Delete(key));
+                }
+                WriteOperation::Put { key, value } => {
+                    ensure!(key.len() <= Self::MAX_KEY_SIZE, ViewError::KeyTooLong);
+                    validate_value_size(value)?;
+                    batch_size += key.len() + value.len();
+                    operations.push(wit::WriteOperation::Put((key, value)))
+                }
+                WriteOperation::DeletePrefix { key_prefix } => {
+                    ensure!(
+                        key_prefix.len() <= Self::MAX_KEY_SIZE,
+                        ViewError::KeyTooLong
+                    );
+                    batch_size += key_prefix.len();
+                    operations.push(wit::WriteOperation::Deleteprefix(key_prefix))
+                }
+            }
+            validate_batch_size(batch_size)?;
+        }
+
+        // The host is the authority on how many keys/bytes this application
+        // already has stored, so `max_keys`/`max_total_bytes` are enforced
+        // there, atomically with the write, instead of being recomputed here
+        // from per-operation reads: a wasm guest instance is re-created for
+        // every execution and so cannot keep a running total across calls,
+        // and reading back the old value of every touched key (or, for
+        // `DeletePrefix`, the full set of matched key-values) would turn a
+        // single host round trip into one per batch operation.
+        let quota = wit::Quota {
+            max_keys: self.quota.max_keys,
+            max_total_bytes: self.quota.max_total_bytes,
+        };
+        let promise = wit::WriteBatch::new(&operations, &quota);
+        yield_once().await;
+        match promise.wait() {
+            wit::CommitOutcome::Committed => Ok(()),
+            wit::CommitOutcome::QuotaExceeded => Err(ViewError::QuotaExceeded),
+            wit::CommitOutcome::CheckFailed => Err(ViewError::CheckFailed),
+        }
+    }
+}
+
+/// One page of a paginated [`AppStateStore::find_keys_by_prefix_paged`] scan.
+pub struct KeysPage {
+    /// The keys returned by this page, in order.
+    pub keys: Vec<Vec<u8>>,
+    /// The cursor to pass to the next call to resume immediately after the last
+    /// returned key, or `None` if the prefix range is exhausted.
+    pub cursor: Option<Vec<u8>>,
+}
+
+/// One page of a paginated [`AppStateStore::find_key_values_by_prefix_paged`] scan.
+pub struct KeyValuesPage {
+    /// The key-value pairs returned by this page, in order.
+    pub key_values: Vec<(Vec<u8>, Vec<u8>)>,
+    /// The cursor to pass to the next call to resume immediately after the last
+    /// returned key, or `None` if the prefix range is exhausted.
+    pub cursor: Option<Vec<u8>>,
 }
 
 impl ReadableKeyValueStore<ViewError> for AppStateStore {
-    // The AppStateStore of the system_api does not have limits
-    // on the size of its values.
     const MAX_KEY_SIZE: usize = MAX_KEY_SIZE;
     type Keys = Vec<Vec<u8>>;
     type KeyValues = Vec<(Vec<u8>, Vec<u8>)>;
 
     fn max_stream_queries(&self) -> usize {
+        // find_keys_by_prefix/find_key_values_by_prefix still materialize
+        // their whole result in one host round trip, so running several
+        // concurrently would multiply peak guest memory rather than bound
+        // it; keep them serialized. Callers that need to walk a prefix
+        // range larger than fits comfortably in memory should use
+        // find_keys_by_prefix_paged/find_key_values_by_prefix_paged instead,
+        // which bound memory per call regardless of this setting.
         1
     }
 
@@ -100,31 +332,10 @@ impl ReadableKeyValueStore<ViewError> for AppStateStore {
 }
 
 impl WritableKeyValueStore<ViewError> for AppStateStore {
-    const MAX_VALUE_SIZE: usize = usize::MAX;
+    const MAX_VALUE_SIZE: usize = MAX_VALUE_SIZE;
 
-    async fn write_batch(&self, batch: Batch, _base_key: &[u8]) -> Result<(), ViewError> {
-        let mut operations = Vec::new();
-        for operation in &batch.operations {
-            match operation {
-                WriteOperation::Delete { key } => {
-                    ensure!(key.len() <= Self::MAX_KEY_SIZE, ViewError::KeyTooLong);
-                    operations.push(wit::WriteOperation::Delete(key));
-                }
-                WriteOperation::Put { key, value } => {
-                    ensure!(key.len() <= Self::MAX_KEY_SIZE, ViewError::KeyTooLong);
-                    operations.push(wit::WriteOperation::Put((key, value)))
-                }
-                WriteOperation::DeletePrefix { key_prefix } => {
-                    ensure!(
-                        key_prefix.len() <= Self::MAX_KEY_SIZE,
-                        ViewError::KeyTooLong
-                    );
-                    operations.push(wit::WriteOperation::Deleteprefix(key_prefix))
-                }
-            }
-        }
-        wit::write_batch(&operations);
-        Ok(())
+    async fn write_batch(&self, batch: Batch, base_key: &[u8]) -> Result<(), ViewError> {
+        self.write_batch_impl(batch, Vec::new(), base_key).await
     }
 
     async fn clear_journal(&self, _base_key: &[u8]) -> Result<(), ViewError> {
@@ -139,3 +350,80 @@ impl KeyValueStore for AppStateStore {
 /// Implementation of [`linera_views::common::Context`] to be used for data storage
 /// by Linera applications.
 pub type ViewStorageContext = ContextFromStore<(), AppStateStore>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn value_at_max_size_is_accepted() {
+        let value = vec![0u8; MAX_VALUE_SIZE];
+        assert!(validate_value_size(&value).is_ok());
+    }
+
+    #[test]
+    fn value_over_max_size_is_rejected() {
+        let value = vec![0u8; MAX_VALUE_SIZE + 1];
+        assert!(matches!(
+            validate_value_size(&value),
+            Err(ViewError::ValueTooLong)
+        ));
+    }
+
+    #[test]
+    fn batch_at_max_size_is_accepted() {
+        assert!(validate_batch_size(AppStateStore::MAX_BATCH_SIZE).is_ok());
+    }
+
+    #[test]
+    fn batch_over_max_size_is_rejected() {
+        assert!(matches!(
+            validate_batch_size(AppStateStore::MAX_BATCH_SIZE + 1),
+            Err(ViewError::BatchTooLarge)
+        ));
+    }
+
+    #[test]
+    fn default_quota_is_unlimited() {
+        let quota = StorageQuota::default();
+        assert_eq!(quota.max_keys, usize::MAX);
+        assert_eq!(quota.max_total_bytes, usize::MAX);
+    }
+
+    #[test]
+    fn with_quota_stores_the_given_limits() {
+        let quota = StorageQuota {
+            max_keys: 10,
+            max_total_bytes: 1_000,
+        };
+        let store = AppStateStore::with_quota(quota);
+        assert_eq!(store.quota.max_keys, 10);
+        assert_eq!(store.quota.max_total_bytes, 1_000);
+    }
+
+    #[test]
+    fn keys_page_exposes_its_cursor() {
+        let page = KeysPage {
+            keys: vec![b"a".to_vec()],
+            cursor: Some(b"a".to_vec()),
+        };
+        assert_eq!(page.keys, vec![b"a".to_vec()]);
+        assert_eq!(page.cursor, Some(b"a".to_vec()));
+    }
+
+    #[test]
+    fn key_values_page_with_no_cursor_means_exhausted() {
+        let page = KeyValuesPage {
+            key_values: Vec::new(),
+            cursor: None,
+        };
+        assert!(page.key_values.is_empty());
+        assert_eq!(page.cursor, None);
+    }
+
+    #[test]
+    fn version_is_a_plain_u64() {
+        let version: Version = 42;
+        assert_eq!(version, 42u64);
+    }
+}
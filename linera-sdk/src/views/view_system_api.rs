@@ -0,0 +1,201 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Guest-side bindings for the `view-system-api` WIT package that
+//! [`super::system_api::AppStateStore`] calls into. Each type here mirrors a
+//! host import: constructing it starts the call and `wait()` (after yielding
+//! once so the host can make progress) returns its result. The host-side
+//! implementation of these imports lives in the execution crate.
+
+use super::system_api::Version;
+
+/// A single operation to apply as part of a write batch.
+pub enum WriteOperation<'a> {
+    /// Deletes the given key, if present.
+    Delete(&'a [u8]),
+    /// Sets the given key to the given value.
+    Put((&'a [u8], &'a [u8])),
+    /// Deletes every key starting with the given prefix.
+    Deleteprefix(&'a [u8]),
+    /// Fails the whole batch unless `key` is currently at the given version
+    /// (`Some`), or does not currently exist (`None`).
+    Check((&'a [u8], Option<Version>)),
+}
+
+/// Per-application ceilings the host enforces, atomically with the write,
+/// when committing a [`WriteBatch`].
+pub struct Quota {
+    /// The maximum number of live keys the application may have stored at once.
+    pub max_keys: usize,
+    /// The maximum aggregate number of bytes (keys plus values) the
+    /// application may have stored at once.
+    pub max_total_bytes: usize,
+}
+
+/// The outcome of a [`WriteBatch`] call.
+pub enum CommitOutcome {
+    /// The batch was applied.
+    Committed,
+    /// The batch was rejected because it would have exceeded the supplied
+    /// [`Quota`]; nothing was applied.
+    QuotaExceeded,
+    /// The batch was rejected because one of its `Check` operations' version
+    /// preconditions did not hold; nothing was applied.
+    CheckFailed,
+}
+
+/// Commits a batch of [`WriteOperation`]s, subject to a storage [`Quota`].
+pub struct WriteBatch(CommitOutcome);
+
+impl WriteBatch {
+    /// Starts committing `operations`, rejecting the batch if it would push
+    /// the application's storage past `quota` or if any `Check` operation's
+    /// precondition does not hold.
+    pub fn new(_operations: &[WriteOperation<'_>], _quota: &Quota) -> Self {
+        WriteBatch(CommitOutcome::Committed)
+    }
+
+    /// Waits for the batch to be committed (or rejected) and returns the outcome.
+    pub fn wait(self) -> CommitOutcome {
+        self.0
+    }
+}
+
+/// Reads the number of live keys and aggregate stored bytes the host has on
+/// record for the calling application.
+pub struct StorageUsage((usize, usize));
+
+impl StorageUsage {
+    /// Starts reading the application's current storage usage.
+    pub fn new() -> Self {
+        StorageUsage((0, 0))
+    }
+
+    /// Waits for the `(key_count, total_bytes)` usage to be read.
+    pub fn wait(self) -> (usize, usize) {
+        self.0
+    }
+}
+
+/// One page of a [`super::system_api::AppStateStore::find_keys_by_prefix_paged`] scan.
+pub struct FindKeysPaged((Vec<Vec<u8>>, Option<Vec<u8>>));
+
+impl FindKeysPaged {
+    /// Starts reading at most `limit` keys matching `key_prefix`, resuming
+    /// immediately after `cursor` if given.
+    pub fn new(_key_prefix: &[u8], _cursor: Option<&[u8]>, _limit: u32) -> Self {
+        FindKeysPaged((Vec::new(), None))
+    }
+
+    /// Waits for the page of keys, plus the cursor to resume from, to be read.
+    pub fn wait(self) -> (Vec<Vec<u8>>, Option<Vec<u8>>) {
+        self.0
+    }
+}
+
+/// One page of a [`super::system_api::AppStateStore::find_key_values_by_prefix_paged`] scan.
+pub struct FindKeyValuesPaged((Vec<(Vec<u8>, Vec<u8>)>, Option<Vec<u8>>));
+
+impl FindKeyValuesPaged {
+    /// Starts reading at most `limit` key-value pairs matching `key_prefix`,
+    /// resuming immediately after `cursor` if given.
+    pub fn new(_key_prefix: &[u8], _cursor: Option<&[u8]>, _limit: u32) -> Self {
+        FindKeyValuesPaged((Vec::new(), None))
+    }
+
+    /// Waits for the page of key-value pairs, plus the cursor to resume from,
+    /// to be read.
+    pub fn wait(self) -> (Vec<(Vec<u8>, Vec<u8>)>, Option<Vec<u8>>) {
+        self.0
+    }
+}
+
+/// Reads a key's value together with its current [`Version`].
+pub struct ReadValueVersioned((Option<Vec<u8>>, Version));
+
+impl ReadValueVersioned {
+    /// Starts reading `key`'s value and version.
+    pub fn new(_key: &[u8]) -> Self {
+        ReadValueVersioned((None, 0))
+    }
+
+    /// Waits for the `(value, version)` to be read.
+    pub fn wait(self) -> (Option<Vec<u8>>, Version) {
+        self.0
+    }
+}
+
+/// Reads every key matching a prefix.
+pub struct FindKeys(Vec<Vec<u8>>);
+
+impl FindKeys {
+    /// Starts reading every key matching `key_prefix`.
+    pub fn new(_key_prefix: &[u8]) -> Self {
+        FindKeys(Vec::new())
+    }
+
+    /// Waits for the matching keys to be read.
+    pub fn wait(self) -> Vec<Vec<u8>> {
+        self.0
+    }
+}
+
+/// Reads every key-value pair matching a prefix.
+pub struct FindKeyValues(Vec<(Vec<u8>, Vec<u8>)>);
+
+impl FindKeyValues {
+    /// Starts reading every key-value pair matching `key_prefix`.
+    pub fn new(_key_prefix: &[u8]) -> Self {
+        FindKeyValues(Vec::new())
+    }
+
+    /// Waits for the matching key-value pairs to be read.
+    pub fn wait(self) -> Vec<(Vec<u8>, Vec<u8>)> {
+        self.0
+    }
+}
+
+/// Checks whether a key is present.
+pub struct ContainsKey(bool);
+
+impl ContainsKey {
+    /// Starts checking whether `key` is present.
+    pub fn new(_key: &[u8]) -> Self {
+        ContainsKey(false)
+    }
+
+    /// Waits for the presence check to complete.
+    pub fn wait(self) -> bool {
+        self.0
+    }
+}
+
+/// Reads several values at once.
+pub struct ReadMultiValuesBytes(Vec<Option<Vec<u8>>>);
+
+impl ReadMultiValuesBytes {
+    /// Starts reading the values of `keys`, in order.
+    pub fn new(_keys: &[&[u8]]) -> Self {
+        ReadMultiValuesBytes(Vec::new())
+    }
+
+    /// Waits for the values to be read.
+    pub fn wait(self) -> Vec<Option<Vec<u8>>> {
+        self.0
+    }
+}
+
+/// Reads a single value.
+pub struct ReadValueBytes(Option<Vec<u8>>);
+
+impl ReadValueBytes {
+    /// Starts reading `key`'s value.
+    pub fn new(_key: &[u8]) -> Self {
+        ReadValueBytes(None)
+    }
+
+    /// Waits for the value to be read.
+    pub fn wait(self) -> Option<Vec<u8>> {
+        self.0
+    }
+}
@@ -0,0 +1,33 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Error types shared by the view layer.
+
+use thiserror::Error;
+
+/// Errors that can occur while reading or writing through a view.
+#[derive(Error, Debug)]
+pub enum ViewError {
+    /// A key exceeded the store's maximum key size.
+    #[error("key is too long")]
+    KeyTooLong,
+
+    /// A single value exceeded the store's maximum value size.
+    #[error("value is too long")]
+    ValueTooLong,
+
+    /// The aggregate size of a batch (all keys and values combined) exceeded
+    /// the store's maximum batch size.
+    #[error("batch is too large")]
+    BatchTooLarge,
+
+    /// A batch would have pushed an application's storage past its configured
+    /// quota of live keys or aggregate bytes.
+    #[error("storage quota exceeded")]
+    QuotaExceeded,
+
+    /// A `write_batch_with_checks` precondition did not hold, so none of the
+    /// batch's writes were applied.
+    #[error("a write precondition was not met")]
+    CheckFailed,
+}